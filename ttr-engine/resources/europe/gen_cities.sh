@@ -1,3 +0,0 @@
-#!/bin/bash
-
-cat /dev/stdin | cut -d ' ' -f 1 | tr '-' '\n' | sort | uniq | awk '{print (NR - 1) " => " $0 ","}'
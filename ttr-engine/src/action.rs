@@ -0,0 +1,41 @@
+use super::Train;
+
+/// A move made by a player, mirroring the kinds in the protocol's `Move.Kind`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Claim a route, paying with the given cards.
+    Route { route: u32, cards: Vec<Train> },
+    /// Build a station in a city, paying with the given cards.
+    Station { city: u32, cards: Vec<Train> },
+    /// Draw new destination tickets.
+    Tickets,
+    /// Take one of the face up train cards. Slots are numbered from 1, as in `Move.slot`.
+    PickOpen { slot: u32 },
+    /// Take a train card from the top of the deck.
+    PickDeck,
+    /// Return the given tickets out of those just drawn, keeping the rest.
+    ReturnTickets { tickets: Vec<u32> },
+    /// Keep the given tickets out of the initial options.
+    ChooseFirstTickets { tickets: Vec<u32> },
+    /// Pay the extra cards demanded by the cards revealed for a tunnel.
+    PayTunnel { cards: Vec<Train> },
+    /// Give up on a tunnel, keeping the cards that were offered for it.
+    AbandonTunnel,
+}
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        use Action::*;
+        match self {
+            Route { .. } => "claim a route",
+            Station { .. } => "build a station",
+            Tickets => "draw tickets",
+            PickOpen { .. } => "pick a face up card",
+            PickDeck => "pick from the deck",
+            ReturnTickets { .. } => "return tickets",
+            ChooseFirstTickets { .. } => "choose first tickets",
+            PayTunnel { .. } => "pay for a tunnel",
+            AbandonTunnel => "abandon a tunnel",
+        }
+    }
+}
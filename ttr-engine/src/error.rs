@@ -0,0 +1,87 @@
+use thiserror::Error;
+
+/// Reasons the engine refuses a move.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MoveError {
+    #[error("Unknown player {0}")]
+    UnknownPlayer(u32),
+    #[error("The game is over")]
+    GameOver,
+    #[error("It is not player {0}'s turn")]
+    NotYourTurn(u32),
+    #[error("Can't {0} at this point in the game")]
+    UnexpectedAction(&'static str),
+    #[error("Unknown route {0}")]
+    UnknownRoute(u32),
+    #[error("Unknown city {0}")]
+    UnknownCity(u32),
+    #[error("Ticket {0} is not one of the options")]
+    UnknownTicket(u32),
+    #[error("Ticket {0} was given more than once")]
+    DuplicateTicket(u32),
+    #[error("Face up slot {0} is out of range")]
+    InvalidSlot(u32),
+    #[error("Face up slot {0} is empty")]
+    EmptySlot(u32),
+    #[error("A face up locomotive can't be taken as the second card")]
+    SecondLocomotive,
+    #[error("No train cards left to draw")]
+    DeckEmpty,
+    #[error("Card {0} is not in the player's hand")]
+    MissingCard(u8),
+    #[error("Expected {expected} cards but got {actual}")]
+    WrongCardCount { expected: u32, actual: u32 },
+    #[error("Cards don't match the required colour")]
+    WrongColour,
+    #[error("Expected at least {expected} locomotives but got {actual}")]
+    NotEnoughLocomotives { expected: u32, actual: u32 },
+    #[error("Route {route} was already claimed by player {owner}")]
+    RouteClaimed { route: u32, owner: u32 },
+    #[error("City {city} already has a station belonging to player {owner}")]
+    CityTaken { city: u32, owner: u32 },
+    #[error("Route {route} is the other half of route {sibling}, which the player already owns")]
+    OwnSibling { route: u32, sibling: u32 },
+    #[error("Route {route} is closed as route {sibling} is claimed and there are too few players")]
+    SiblingClaimed { route: u32, sibling: u32 },
+    #[error("No stations left")]
+    NoStationsLeft,
+    #[error("Only {0} trains left")]
+    NotEnoughTrains(u32),
+    #[error("No tickets left to draw")]
+    NoTicketsLeft,
+    #[error("Must keep at least {required} tickets, but kept {kept}")]
+    TooFewTickets { kept: u32, required: u32 },
+}
+
+/// Reasons a game can't be set up.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SetupError {
+    #[error("Unknown map id {0}")]
+    UnknownMap(u32),
+    #[error("First player {first} is out of range for {players} players")]
+    InvalidFirstPlayer { first: u32, players: u32 },
+    #[error("The map is for {min} to {max} players, not {players}")]
+    PlayerCount { players: u32, min: u32, max: u32 },
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("Invalid map file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("City {0} is listed more than once")]
+    DuplicateCity(String),
+    #[error("Route or ticket mentions unknown city {0}")]
+    UnknownCity(String),
+    #[error("Route {route} is invalid: {reason}")]
+    InvalidRoute { route: u32, reason: &'static str },
+    #[error("Ticket {ticket} is invalid: {reason}")]
+    InvalidTicket { ticket: u32, reason: &'static str },
+    #[error("Invalid initial deal: {0}")]
+    InvalidDeal(&'static str),
+    #[error("Invalid train deck: {0}")]
+    InvalidDeck(&'static str),
+    #[error("Invalid pieces: {0}")]
+    InvalidPieces(&'static str),
+    #[error("Invalid player count")]
+    InvalidPlayerCount,
+}
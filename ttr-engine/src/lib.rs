@@ -1,12 +1,31 @@
-mod map;
+mod action;
+mod error;
+pub mod map;
+mod moves;
+mod path;
+mod payment;
 mod player;
 mod rand;
+mod score;
+mod tickets;
 mod trains;
+mod view;
 
-use map::Map;
-use player::Player;
+pub use action::Action;
+pub use error::{LoadError, MoveError, SetupError};
+pub use path::Path;
+pub use payment::{Cost, Payment};
+pub use player::Player;
+pub use score::ScoreBreakdown;
+pub use tickets::TicketStatus;
+pub use view::{Opponent, PlayerView, ViewState};
+
+use map::{Builtin, DataMap, Map, MapData};
 use rand::Rand;
-use trains::{Colour, Train, TrainDeck};
+use trains::TrainDeck;
+use ttr_protocol::protos;
+
+pub use trains::{Colour, Train};
 
 pub struct Engine {
     rand: Rand,
@@ -14,6 +33,9 @@ pub struct Engine {
     trains: TrainDeck,
     face_up: FaceUp,
     players: Vec<Player>,
+    first_player: u32,
+    /// Once a player runs low on trains, the player whose turn will end the game.
+    last_player: Option<u32>,
 
     state: GameState,
 }
@@ -21,7 +43,12 @@ pub struct Engine {
 #[derive(Debug)]
 pub enum GameState {
     InitialTickets(Vec<InitialTicketState>),
-    Turn { player: u32, state: TurnState },
+    Turn {
+        player: u32,
+        state: TurnState,
+    },
+    /// The game is over, with each player's final score.
+    Finished(Vec<ScoreBreakdown>),
 }
 
 #[derive(Debug)]
@@ -33,8 +60,25 @@ pub struct InitialTicketState {
 #[derive(Debug)]
 pub enum TurnState {
     Start,
-    PickAnotherTicket,
+    /// One train card has been taken and the player must take a second.
+    PickAnotherTrain {
+        first: Pick,
+    },
     SelectingTickets(Vec<&'static Ticket>),
+    /// A tunnel claim is waiting for the player to pay `extra` more cards or give up.
+    Tunnel {
+        route: &'static Route,
+        cards: Vec<Train>,
+        revealed: Vec<Train>,
+        extra: u32,
+    },
+}
+
+/// The first train card taken in a turn. Cards from the deck stay hidden.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Pick {
+    Open(Train),
+    Deck,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -49,20 +93,66 @@ pub struct Ticket {
     pub city0: &'static City,
     pub city1: &'static City,
     pub value: u32,
+    /// Whether the ticket is dealt from the long ticket deck.
+    pub long: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub id: u32,
+    pub city0: &'static City,
+    pub city1: &'static City,
+    pub length: u32,
+    /// `None` for grey routes, which can be claimed with any single colour.
+    pub colour: Option<Colour>,
+    pub tunnel: bool,
+    /// Number of locomotives required, non-zero only for ferries.
+    pub locomotives: u32,
+    /// The other half of a double route, if any.
+    pub sibling: Option<u32>,
 }
 
+/// The face up train cards. Slots are empty only when the deck and discard pile have run out.
 #[derive(Debug, Copy, Clone)]
-struct FaceUp([Train; 5]);
+struct FaceUp([Option<Train>; 5]);
 
 impl Engine {
-    pub fn new<M: Map + 'static>(seed: u32, num_players: u32) -> Self {
+    pub fn new<M: Builtin>(
+        seed: u32,
+        num_players: u32,
+        first_player: u32,
+    ) -> Result<Self, SetupError> {
+        Engine::with_map_data(M::data(), seed, num_players, first_player)
+    }
+
+    /// Sets up a game on a map loaded from a data file.
+    pub fn with_map_data(
+        data: &'static MapData,
+        seed: u32,
+        num_players: u32,
+        first_player: u32,
+    ) -> Result<Self, SetupError> {
+        let count = &data.players;
+        if num_players < count.min || num_players > count.max {
+            return Err(SetupError::PlayerCount {
+                players: num_players,
+                min: count.min,
+                max: count.max,
+            });
+        }
+        if first_player >= num_players {
+            return Err(SetupError::InvalidFirstPlayer {
+                first: first_player,
+                players: num_players,
+            });
+        }
         let mut rand = Rand::new(seed);
-        let mut map = Box::new(M::new(&mut rand));
-        let mut trains = TrainDeck::new();
+        let mut map: Box<dyn Map> = Box::new(DataMap::new(data, &mut rand));
+        let mut trains = TrainDeck::new(&data.deck);
         let face_up = FaceUp::new(&mut rand, &mut trains);
-        let mut players = (0..num_players)
+        let players = (0..num_players)
             .map(|id| {
-                let mut p = Player::new(id);
+                let mut p = Player::new(id, &data.pieces);
                 p.hand = trains.deal(&mut rand, 4);
                 p
             })
@@ -76,56 +166,552 @@ impl Engine {
                 })
                 .collect(),
         );
-        Engine {
+        Ok(Engine {
             rand,
             map,
             trains,
             face_up,
             players,
+            first_player,
+            last_player: None,
             state,
-        }
+        })
+    }
+
+    /// Sets up a game on the map with the given protocol id.
+    pub fn with_map_id(
+        map: u32,
+        seed: u32,
+        num_players: u32,
+        first_player: u32,
+    ) -> Result<Self, SetupError> {
+        let data = map::lookup(map)?;
+        Engine::with_map_data(data, seed, num_players, first_player)
+    }
+
+    /// Sets up the game described by the host's `Welcome` message.
+    pub fn from_welcome(options: &protos::Welcome_Options) -> Result<Self, SetupError> {
+        // The app seeds its generator with the low 32 bits of the seed.
+        let seed = options.seed as u32;
+        Engine::with_map_id(options.map, seed, options.players, options.firstPlayer)
     }
 
     pub fn state(&self) -> &GameState {
         &self.state
     }
+
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    pub fn map(&self) -> &dyn Map {
+        self.map.as_ref()
+    }
+
+    /// The longest continuous path through a player's routes, or `None` if there is no such player.
+    pub fn longest_path(&self, player: u32) -> Option<Path> {
+        let p = self.players.get(player as usize)?;
+        Some(path::longest_path(&p.routes))
+    }
+
+    /// Which of a player's tickets are complete, counting routes borrowed by their stations.
+    /// `None` if there is no such player.
+    pub fn ticket_status(&self, player: u32) -> Option<TicketStatus> {
+        if (player as usize) < self.players.len() {
+            Some(tickets::ticket_status(&self.players, player))
+        } else {
+            None
+        }
+    }
+
+    /// Scores every player as if the game ended now.
+    pub fn scores(&self) -> Vec<ScoreBreakdown> {
+        score::score(self.map.as_ref(), &self.players)
+    }
+
+    pub fn apply(&mut self, player: u32, action: Action) -> Result<(), MoveError> {
+        if player as usize >= self.players.len() {
+            return Err(MoveError::UnknownPlayer(player));
+        }
+        if let GameState::Finished(_) = self.state {
+            return Err(MoveError::GameOver);
+        }
+        if !self.state.action_required(player) {
+            return Err(MoveError::NotYourTurn(player));
+        }
+
+        let name = action.name();
+        match action {
+            Action::ChooseFirstTickets { tickets } => {
+                self.choose_first_tickets(name, player, tickets)
+            }
+            Action::Route { route, cards } => {
+                self.expect_turn_state(name, |s| matches!(s, TurnState::Start))?;
+                self.claim_route(player, route, cards)
+            }
+            Action::Station { city, cards } => {
+                self.expect_turn_state(name, |s| matches!(s, TurnState::Start))?;
+                self.build_station(player, city, cards)
+            }
+            Action::Tickets => {
+                self.expect_turn_state(name, |s| matches!(s, TurnState::Start))?;
+                self.draw_tickets()
+            }
+            Action::ReturnTickets { tickets } => self.return_tickets(name, player, tickets),
+            Action::PickOpen { slot } => {
+                self.expect_turn_state(name, TurnState::can_pick_train)?;
+                self.pick_open(player, slot)
+            }
+            Action::PickDeck => {
+                self.expect_turn_state(name, TurnState::can_pick_train)?;
+                self.pick_deck(player)
+            }
+            Action::PayTunnel { cards } => self.pay_tunnel(name, player, cards),
+            Action::AbandonTunnel => {
+                self.expect_turn_state(name, |s| matches!(s, TurnState::Tunnel { .. }))?;
+                self.discard_revealed();
+                self.next_turn();
+                Ok(())
+            }
+        }
+    }
+
+    fn choose_first_tickets(
+        &mut self,
+        action: &'static str,
+        player: u32,
+        tickets: Vec<u32>,
+    ) -> Result<(), MoveError> {
+        let states = match &mut self.state {
+            GameState::InitialTickets(states) => states,
+            _ => return Err(MoveError::UnexpectedAction(action)),
+        };
+        let state = &mut states[player as usize];
+        let selected = find_tickets(&state.options, &tickets)?;
+        let required = self.map.initial_keep();
+        if (selected.len() as u32) < required {
+            return Err(MoveError::TooFewTickets {
+                kept: selected.len() as u32,
+                required,
+            });
+        }
+        state.selected = Some(selected);
+
+        if states.iter().all(|s| s.selected.is_some()) {
+            for (player, state) in self.players.iter_mut().zip(states.drain(..)) {
+                let selected = state.selected.unwrap();
+                let unchosen = state
+                    .options
+                    .into_iter()
+                    .filter(|t| !selected.contains(t))
+                    .collect();
+                self.map.return_initial_tickets(unchosen);
+                player.tickets = selected;
+            }
+            self.state = GameState::Turn {
+                player: self.first_player,
+                state: TurnState::Start,
+            };
+        }
+        Ok(())
+    }
+
+    fn claim_route(&mut self, player: u32, route: u32, cards: Vec<Train>) -> Result<(), MoveError> {
+        let route = self
+            .map
+            .routes()
+            .get(route as usize)
+            .ok_or(MoveError::UnknownRoute(route))?;
+        self.check_route(player, route)?;
+        let p = &self.players[player as usize];
+        let colour = Cost::route(route).check(&p.hand, &cards)?;
+
+        if route.tunnel {
+            let revealed = self.trains.deal(&mut self.rand, 3);
+            let extra = revealed
+                .iter()
+                .filter(|c| c.colour() == colour || c.colour() == Colour::Rainbow)
+                .count() as u32;
+            if extra > 0 {
+                self.set_turn_state(TurnState::Tunnel {
+                    route,
+                    cards,
+                    revealed,
+                    extra,
+                });
+                return Ok(());
+            }
+            self.trains.discard(revealed);
+        }
+
+        self.finish_claim(player, route, cards);
+        Ok(())
+    }
+
+    fn pay_tunnel(
+        &mut self,
+        action: &'static str,
+        player: u32,
+        extra_cards: Vec<Train>,
+    ) -> Result<(), MoveError> {
+        let (route, cards, extra) = match self.turn_state() {
+            Some(TurnState::Tunnel {
+                route,
+                cards,
+                extra,
+                ..
+            }) => (*route, cards, *extra),
+            _ => return Err(MoveError::UnexpectedAction(action)),
+        };
+        let hand = &self.players[player as usize].hand;
+        let colour = Cost::route(route).check(hand, cards)?;
+        Cost::tunnel(extra, colour).check(&payment::remaining(hand, cards)?, &extra_cards)?;
+        let mut all = cards.clone();
+        all.extend(extra_cards);
+
+        self.discard_revealed();
+        self.finish_claim(player, route, all);
+        Ok(())
+    }
+
+    /// Discards the cards revealed for a tunnel once it is paid for or abandoned, ahead of any
+    /// cards paid for it. The app's order is a guess, not yet confirmed from a capture; it decides
+    /// which cards are dealt after the next reshuffle.
+    fn discard_revealed(&mut self) {
+        if let GameState::Turn {
+            state: TurnState::Tunnel { revealed, .. },
+            ..
+        } = &mut self.state
+        {
+            self.trains.discard(revealed.drain(..));
+        }
+    }
+
+    fn finish_claim(&mut self, player: u32, route: &'static Route, cards: Vec<Train>) {
+        let p = &mut self.players[player as usize];
+        p.remove_cards(&cards);
+        p.trains -= route.length;
+        p.routes.push(route);
+        self.trains.discard(cards);
+        self.next_turn();
+    }
+
+    fn build_station(
+        &mut self,
+        player: u32,
+        city: u32,
+        cards: Vec<Train>,
+    ) -> Result<(), MoveError> {
+        let city = self
+            .map
+            .cities()
+            .get(city as usize)
+            .ok_or(MoveError::UnknownCity(city))?;
+
+        self.check_station(player, city)?;
+        let p = &mut self.players[player as usize];
+        Cost::station(p.stations.len() as u32).check(&p.hand, &cards)?;
+        p.remove_cards(&cards);
+        p.stations.push(city);
+        self.trains.discard(cards);
+        self.next_turn();
+        Ok(())
+    }
+
+    fn draw_tickets(&mut self) -> Result<(), MoveError> {
+        // Near the end of the deck there may be fewer than three to choose from.
+        let tickets: Vec<_> = (0..3).map_while(|_| self.map.draw_ticket()).collect();
+        if tickets.is_empty() {
+            return Err(MoveError::NoTicketsLeft);
+        }
+        self.set_turn_state(TurnState::SelectingTickets(tickets));
+        Ok(())
+    }
+
+    fn return_tickets(
+        &mut self,
+        action: &'static str,
+        player: u32,
+        tickets: Vec<u32>,
+    ) -> Result<(), MoveError> {
+        let drawn = match self.turn_state() {
+            Some(TurnState::SelectingTickets(drawn)) => drawn,
+            _ => return Err(MoveError::UnexpectedAction(action)),
+        };
+        find_tickets(drawn, &tickets)?;
+        let (returned, kept): (Vec<_>, Vec<_>) =
+            drawn.iter().partition(|t| tickets.contains(&t.id));
+        if kept.is_empty() {
+            return Err(MoveError::TooFewTickets {
+                kept: 0,
+                required: 1,
+            });
+        }
+
+        self.map.return_tickets(returned);
+        self.players[player as usize].tickets.extend(kept);
+        self.next_turn();
+        Ok(())
+    }
+
+    fn pick_open(&mut self, player: u32, slot: u32) -> Result<(), MoveError> {
+        let idx = slot.checked_sub(1).ok_or(MoveError::InvalidSlot(slot))? as usize;
+        let card = match self.face_up.0.get(idx) {
+            None => return Err(MoveError::InvalidSlot(slot)),
+            Some(None) => return Err(MoveError::EmptySlot(slot)),
+            Some(Some(card)) => *card,
+        };
+        let first = matches!(self.turn_state(), Some(TurnState::Start));
+        if !first && card.colour() == Colour::Rainbow {
+            return Err(MoveError::SecondLocomotive);
+        }
+
+        self.face_up.draw(&mut self.rand, &mut self.trains, idx);
+        self.players[player as usize].hand.push(card);
+        // A face up locomotive is the whole turn.
+        if first && card.colour() != Colour::Rainbow {
+            self.picked_first(Pick::Open(card));
+        } else {
+            self.next_turn();
+        }
+        Ok(())
+    }
+
+    fn pick_deck(&mut self, player: u32) -> Result<(), MoveError> {
+        let card = self
+            .trains
+            .deal_one(&mut self.rand)
+            .ok_or(MoveError::DeckEmpty)?;
+        self.players[player as usize].hand.push(card);
+        match self.turn_state() {
+            Some(TurnState::Start) => self.picked_first(Pick::Deck),
+            _ => self.next_turn(),
+        }
+        Ok(())
+    }
+
+    fn picked_first(&mut self, first: Pick) {
+        // If nothing can legally be taken second the turn just ends.
+        let can_pick = self.trains.cards().next().is_some()
+            || self
+                .face_up
+                .0
+                .iter()
+                .flatten()
+                .any(|c| c.colour() != Colour::Rainbow);
+        if can_pick {
+            self.set_turn_state(TurnState::PickAnotherTrain { first });
+        } else {
+            self.next_turn();
+        }
+    }
+
+    /// Checks everything about claiming a route except the cards.
+    fn check_route(&self, player: u32, route: &Route) -> Result<(), MoveError> {
+        if let Some(owner) = self.route_owner(route.id) {
+            return Err(MoveError::RouteClaimed {
+                route: route.id,
+                owner,
+            });
+        }
+        if let Some(sibling) = route.sibling {
+            match self.route_owner(sibling) {
+                Some(owner) if owner == player => {
+                    return Err(MoveError::OwnSibling {
+                        route: route.id,
+                        sibling,
+                    });
+                }
+                Some(_) if (self.players.len() as u32) < self.map.data().players.doubles => {
+                    return Err(MoveError::SiblingClaimed {
+                        route: route.id,
+                        sibling,
+                    });
+                }
+                _ => {}
+            }
+        }
+        let p = &self.players[player as usize];
+        if p.trains < route.length {
+            return Err(MoveError::NotEnoughTrains(p.trains));
+        }
+        Ok(())
+    }
+
+    /// Checks everything about building a station except the cards.
+    fn check_station(&self, player: u32, city: &City) -> Result<(), MoveError> {
+        if let Some(owner) = self.station_owner(city.id) {
+            return Err(MoveError::CityTaken {
+                city: city.id,
+                owner,
+            });
+        }
+        if self.players[player as usize].stations_left() == 0 {
+            return Err(MoveError::NoStationsLeft);
+        }
+        Ok(())
+    }
+
+    fn route_owner(&self, route: u32) -> Option<u32> {
+        self.players
+            .iter()
+            .find(|p| p.routes.iter().any(|r| r.id == route))
+            .map(|p| p.id)
+    }
+
+    fn station_owner(&self, city: u32) -> Option<u32> {
+        self.players
+            .iter()
+            .find(|p| p.stations.iter().any(|c| c.id == city))
+            .map(|p| p.id)
+    }
+
+    fn turn_state(&self) -> Option<&TurnState> {
+        match &self.state {
+            GameState::Turn { state, .. } => Some(state),
+            _ => None,
+        }
+    }
+
+    fn expect_turn_state(
+        &self,
+        action: &'static str,
+        f: impl FnOnce(&TurnState) -> bool,
+    ) -> Result<(), MoveError> {
+        match self.turn_state() {
+            Some(state) if f(state) => Ok(()),
+            _ => Err(MoveError::UnexpectedAction(action)),
+        }
+    }
+
+    fn set_turn_state(&mut self, new: TurnState) {
+        if let GameState::Turn { state, .. } = &mut self.state {
+            *state = new;
+        }
+    }
+
+    fn next_turn(&mut self) {
+        let player = match self.state {
+            GameState::Turn { player, .. } => player,
+            _ => return,
+        };
+        if self.last_player == Some(player) {
+            self.state = GameState::Finished(self.scores());
+            return;
+        }
+        // Running low on trains gives everyone, this player included, one more turn.
+        if self.last_player.is_none()
+            && self.players[player as usize].trains <= self.map.data().pieces.last_round
+        {
+            self.last_player = Some(player);
+        }
+        self.state = GameState::Turn {
+            player: (player + 1) % self.players.len() as u32,
+            state: TurnState::Start,
+        };
+        // Discards may have made cards available for slots that were left empty. This only deals
+        // when a slot is empty; when the app refills such slots is assumed, not yet confirmed from
+        // a capture.
+        self.face_up.refill(&mut self.rand, &mut self.trains);
+    }
 }
 
 impl GameState {
     pub fn action_required(&self, player: u32) -> bool {
+        use GameState::*;
         match self {
-            InitialTickets(players) => players[player as usize].is_some(),
-            Turn { player: turn_player, _ } => player == turn_player,
+            InitialTickets(players) => players[player as usize].selected.is_none(),
+            Turn {
+                player: turn_player,
+                ..
+            } => player == *turn_player,
+            Finished(_) => false,
         }
     }
 }
 
+impl TurnState {
+    fn can_pick_train(&self) -> bool {
+        matches!(self, TurnState::Start | TurnState::PickAnotherTrain { .. })
+    }
+}
+
+fn find_tickets(
+    options: &[&'static Ticket],
+    ids: &[u32],
+) -> Result<Vec<&'static Ticket>, MoveError> {
+    ids.iter()
+        .enumerate()
+        .map(|(i, &id)| {
+            if ids[..i].contains(&id) {
+                return Err(MoveError::DuplicateTicket(id));
+            }
+            options
+                .iter()
+                .copied()
+                .find(|t| t.id == id)
+                .ok_or(MoveError::UnknownTicket(id))
+        })
+        .collect()
+}
+
 impl FaceUp {
     fn new(rand: &mut Rand, deck: &mut TrainDeck) -> Self {
-        use std::convert::TryInto as _;
-        let mut cards = FaceUp(deck.deal(rand, 5).as_slice().try_into().unwrap());
-        cards.check_for_rainbow(rand, deck);
+        let mut cards = FaceUp([None; 5]);
+        cards.refill(rand, deck);
 
         cards
     }
 
+    /// Deals into any empty slots, then checks for too many locomotives.
+    fn refill(&mut self, rand: &mut Rand, deck: &mut TrainDeck) {
+        if self.0.iter().all(Option::is_some) {
+            return;
+        }
+        for slot in self.0.iter_mut().filter(|s| s.is_none()) {
+            *slot = deck.deal_one(rand);
+        }
+        self.check_for_rainbow(rand, deck);
+    }
+
+    /// Replaces all five cards while three or more are locomotives, unless the cards left make a
+    /// display with fewer than three locomotives impossible, in which case it's left as is.
+    ///
+    /// The exception is assumed, not yet confirmed from a capture: it only stops the redeal
+    /// looping forever when the cards left are mostly locomotives.
     fn check_for_rainbow(&mut self, rand: &mut Rand, deck: &mut TrainDeck) {
-        while self.num_rainbow() >= 3 {
-            deck.discard(self.0.as_ref().iter().copied());
-            self.0.copy_from_slice(deck.deal(rand, 5).as_slice());
+        while self.num_rainbow() >= 3 && self.can_reset(deck) {
+            deck.discard(self.0.iter_mut().filter_map(Option::take));
+            for slot in self.0.iter_mut() {
+                *slot = deck.deal_one(rand);
+            }
         }
     }
 
+    fn can_reset(&self, deck: &TrainDeck) -> bool {
+        let cards: Vec<_> = deck
+            .cards()
+            .chain(self.0.iter().flatten().copied())
+            .collect();
+        let others = cards
+            .iter()
+            .filter(|c| c.colour() != Colour::Rainbow)
+            .count();
+        let shown = cards.len().min(self.0.len());
+        shown.saturating_sub(others) < 3
+    }
+
     fn num_rainbow(&self) -> usize {
         self.0
             .iter()
+            .flatten()
             .filter(|x| x.colour() == Colour::Rainbow)
             .count()
     }
 
-    fn draw(&mut self, rand: &mut Rand, deck: &mut TrainDeck, slot: usize) -> Train {
-        let result = std::mem::replace(&mut self.0[slot], deck.deal_one(rand));
-        self.check_for_rainbow(rand, deck);
+    fn draw(&mut self, rand: &mut Rand, deck: &mut TrainDeck, slot: usize) -> Option<Train> {
+        let result = self.0[slot].take();
+        self.refill(rand, deck);
         result
     }
 }
@@ -134,22 +720,416 @@ impl FaceUp {
 mod test {
     use super::*;
 
+    fn train(id: u8) -> Train {
+        map::Europe::data().train(id).unwrap()
+    }
+
     #[test]
     fn test_face_up() {
         use Colour::*;
 
-        let engine = Engine::new::<map::Europe>(27683789, 2);
-        let colours: Vec<_> = engine.face_up.0.iter().map(|x| x.colour()).collect();
+        let engine = Engine::new::<map::Europe>(27683789, 2, 0).unwrap();
+        let colours: Vec<_> = engine
+            .face_up
+            .0
+            .iter()
+            .map(|x| x.unwrap().colour())
+            .collect();
         assert_eq!(colours, vec![White, Orange, Pink, Green, Rainbow]);
     }
 
+    #[test]
+    fn test_face_up_short() {
+        let mut rand = Rand::new(1);
+        let mut deck = TrainDeck::new(&map::Europe::data().deck);
+        let mut dealt = deck.deal(&mut rand, 110);
+        dealt.sort();
+
+        // With only locomotives left there's no point redealing.
+        deck.discard(dealt.drain(96..));
+        let mut face_up = FaceUp::new(&mut rand, &mut deck);
+        assert_eq!(face_up.num_rainbow(), 5);
+
+        // Once three other cards are available the display gets redealt until it shows them.
+        deck.discard(dealt.drain(..3));
+        face_up.draw(&mut rand, &mut deck, 0);
+        assert_eq!(face_up.num_rainbow(), 2);
+
+        let mut empty = TrainDeck::new(&map::Europe::data().deck);
+        empty.deal(&mut rand, 110);
+        let face_up = FaceUp::new(&mut rand, &mut empty);
+        assert!(face_up.0.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_from_welcome() {
+        use Colour::*;
+
+        let mut options = protos::Welcome_Options::new();
+        options.seed = 18446744071963584756;
+        options.map = map::Europe::ID;
+        options.players = 2;
+        options.firstPlayer = 1;
+        let engine = Engine::from_welcome(&options).unwrap();
+        let mut colours: Vec<_> = engine.players[0].hand.iter().map(|x| x.colour()).collect();
+        colours.sort();
+        assert_eq!(colours, vec![Orange, Red, Green, Green]);
+        assert_eq!(engine.first_player, 1);
+
+        options.map = map::Usa::ID;
+        let engine = Engine::from_welcome(&options).unwrap();
+        assert_eq!(engine.map().routes().len(), 100);
+        assert_eq!(engine.players()[0].stations_left(), 0);
+
+        assert_eq!(
+            Engine::new::<map::Europe>(1, 2, 7).err(),
+            Some(SetupError::InvalidFirstPlayer {
+                first: 7,
+                players: 2
+            })
+        );
+
+        options.players = 6;
+        assert_eq!(
+            Engine::from_welcome(&options).err(),
+            Some(SetupError::PlayerCount {
+                players: 6,
+                min: 2,
+                max: 5
+            })
+        );
+
+        options.map = 1000;
+        assert_eq!(
+            Engine::from_welcome(&options).err(),
+            Some(SetupError::UnknownMap(1000))
+        );
+    }
+
     #[test]
     fn test_hand() {
         use Colour::*;
 
-        let engine = Engine::new::<map::Europe>(18446744071963584756u64 as u32, 2);
+        let engine = Engine::new::<map::Europe>(18446744071963584756u64 as u32, 2, 0).unwrap();
         let mut colours: Vec<_> = engine.players[0].hand.iter().map(|x| x.colour()).collect();
         colours.sort();
         assert_eq!(colours, vec![Orange, Red, Green, Green]);
     }
+
+    fn start_game(seed: u32) -> Engine {
+        let mut engine = Engine::new::<map::Europe>(seed, 2, 0).unwrap();
+        for player in 0..2 {
+            let tickets = match engine.state() {
+                GameState::InitialTickets(states) => states[player as usize].options[..2]
+                    .iter()
+                    .map(|t| t.id)
+                    .collect(),
+                state => panic!("{:?}", state),
+            };
+            engine
+                .apply(player, Action::ChooseFirstTickets { tickets })
+                .unwrap();
+        }
+        engine
+    }
+
+    #[test]
+    fn test_tunnel() {
+        use Colour::*;
+
+        let mut engine = start_game(27683789);
+        let greens: Vec<Train> = (84..96).map(train).collect();
+        engine.players[0].hand = greens.clone();
+
+        // Venezia-Zurich, a green tunnel of length 2.
+        let route = Action::Route {
+            route: 35,
+            cards: greens[..2].to_vec(),
+        };
+        let discarded = engine.trains.discard_len();
+        engine.apply(0, route.clone()).unwrap();
+        let (revealed, extra) = match engine.state() {
+            GameState::Turn {
+                state: TurnState::Tunnel {
+                    revealed, extra, ..
+                },
+                ..
+            } => (revealed.clone(), *extra),
+            state => panic!("{:?}", state),
+        };
+        assert_eq!(revealed.len(), 3);
+        let matching = revealed
+            .iter()
+            .filter(|c| c.colour() == Green || c.colour() == Rainbow)
+            .count();
+        assert_eq!(extra as usize, matching);
+        // The revealed cards stay out of the discard pile until the tunnel is resolved.
+        assert_eq!(engine.trains.discard_len(), discarded);
+
+        engine.apply(0, Action::AbandonTunnel).unwrap();
+        assert_eq!(engine.trains.discard_len(), discarded + 3);
+        assert_eq!(engine.players[0].hand, greens);
+        assert!(engine.players[0].routes.is_empty());
+    }
+
+    #[test]
+    fn test_stations() {
+        let mut engine = start_game(27683789);
+        let city = |name| {
+            engine
+                .map
+                .cities()
+                .iter()
+                .find(|c| c.name == name)
+                .unwrap()
+                .id
+        };
+        let (paris, wien, roma, kyiv) = (city("Paris"), city("Wien"), city("Roma"), city("Kyiv"));
+        let station = |engine: &mut Engine, player, city, cards: &[u8]| {
+            let cards = cards.iter().copied().map(train).collect();
+            engine.apply(player, Action::Station { city, cards })
+        };
+        engine.players[0].hand = (0..12).map(train).collect();
+        engine.players[0].hand.extend((84..96).map(train));
+        engine.players[1].hand = (12..24).map(train).collect();
+
+        station(&mut engine, 0, paris, &[0]).unwrap();
+        assert_eq!(
+            station(&mut engine, 1, paris, &[12]),
+            Err(MoveError::CityTaken {
+                city: paris,
+                owner: 0
+            })
+        );
+        station(&mut engine, 1, wien, &[12]).unwrap();
+        assert_eq!(
+            station(&mut engine, 0, roma, &[1]),
+            Err(MoveError::WrongCardCount {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(
+            station(&mut engine, 0, roma, &[1, 84]),
+            Err(MoveError::WrongColour)
+        );
+        station(&mut engine, 0, roma, &[84, 85]).unwrap();
+        station(&mut engine, 1, paris + 1, &[13, 14]).unwrap();
+        station(&mut engine, 0, kyiv, &[1, 2, 3]).unwrap();
+        station(&mut engine, 1, kyiv + 1, &[15, 16, 17]).unwrap();
+
+        assert_eq!(engine.players[0].stations_left(), 0);
+        assert_eq!(
+            station(&mut engine, 0, kyiv + 2, &[4, 5, 6, 7]),
+            Err(MoveError::NoStationsLeft)
+        );
+        assert_eq!(engine.players[0].hand.len(), 18);
+    }
+
+    #[test]
+    fn test_ferry() {
+        let mut engine = start_game(27683789);
+        engine.players[0].hand = [72, 73, 74, 96, 97].iter().map(|&c| train(c)).collect();
+        let claim = |engine: &mut Engine, cards: &[u8]| {
+            let cards = cards.iter().copied().map(train).collect();
+            // Sevastopol-Sochi, a grey ferry of length 2 needing one locomotive.
+            engine.apply(0, Action::Route { route: 95, cards })
+        };
+
+        assert_eq!(
+            claim(&mut engine, &[72, 73]),
+            Err(MoveError::NotEnoughLocomotives {
+                expected: 1,
+                actual: 0
+            })
+        );
+        claim(&mut engine, &[72, 96]).unwrap();
+        assert_eq!(engine.players[0].routes[0].id, 95);
+    }
+
+    #[test]
+    fn test_pick_locomotive() {
+        let mut engine = start_game(27683789);
+        let rainbow = engine
+            .face_up
+            .0
+            .iter()
+            .position(|c| c.unwrap().colour() == Colour::Rainbow)
+            .unwrap() as u32;
+
+        // Taking a face up locomotive first ends the turn.
+        engine
+            .apply(0, Action::PickOpen { slot: rainbow + 1 })
+            .unwrap();
+        assert!(engine.state().action_required(1));
+
+        engine.apply(1, Action::PickDeck).unwrap();
+        match engine.state() {
+            GameState::Turn {
+                player: 1,
+                state: TurnState::PickAnotherTrain { first: Pick::Deck },
+            } => {}
+            state => panic!("{:?}", state),
+        }
+        engine.face_up.0[2] = Some(train(100));
+        assert_eq!(
+            engine.apply(1, Action::PickOpen { slot: 3 }),
+            Err(MoveError::SecondLocomotive)
+        );
+        engine.apply(1, Action::PickOpen { slot: 1 }).unwrap();
+        assert!(engine.state().action_required(0));
+    }
+
+    #[test]
+    fn test_initial_tickets() {
+        let mut engine = Engine::new::<map::Europe>(27683789, 2, 1).unwrap();
+        let options: Vec<Vec<u32>> = match engine.state() {
+            GameState::InitialTickets(states) => states
+                .iter()
+                .map(|s| s.options.iter().map(|t| t.id).collect())
+                .collect(),
+            state => panic!("{:?}", state),
+        };
+
+        let choose = |tickets: &[u32]| Action::ChooseFirstTickets {
+            tickets: tickets.to_vec(),
+        };
+        assert_eq!(
+            engine.apply(0, choose(&options[0][..1])),
+            Err(MoveError::TooFewTickets {
+                kept: 1,
+                required: 2
+            })
+        );
+        assert_eq!(
+            engine.apply(0, choose(&[options[0][0], options[0][0]])),
+            Err(MoveError::DuplicateTicket(options[0][0]))
+        );
+        engine.apply(0, choose(&options[0][..2])).unwrap();
+        assert_eq!(
+            engine.apply(0, choose(&options[0][..2])),
+            Err(MoveError::NotYourTurn(0))
+        );
+        engine.apply(1, choose(&options[1][1..])).unwrap();
+        assert!(engine.state().action_required(1));
+
+        // Player 0's third short ticket and player 1's first go to the bottom of the deck, while
+        // player 0's long ticket leaves the game.
+        let rest: Vec<_> = std::iter::from_fn(|| engine.map.draw_ticket())
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(&rest[rest.len() - 2..], &[options[0][2], options[1][0]]);
+        assert!(!rest.contains(&options[0][3]));
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut engine = start_game(27683789);
+        assert!(engine.state().action_required(0));
+        assert_eq!(engine.players[1].tickets.len(), 2);
+
+        engine.apply(0, Action::PickDeck).unwrap();
+        assert_eq!(
+            engine.apply(1, Action::PickDeck),
+            Err(MoveError::NotYourTurn(1))
+        );
+        assert_eq!(engine.longest_path(0).unwrap().length, 0);
+        assert!(engine.longest_path(2).is_none());
+        assert!(engine.ticket_status(2).is_none());
+        assert_eq!(
+            engine.apply(0, Action::PickOpen { slot: 6 }),
+            Err(MoveError::InvalidSlot(6))
+        );
+        assert_eq!(
+            engine.apply(0, Action::PickOpen { slot: 0 }),
+            Err(MoveError::InvalidSlot(0))
+        );
+        // Slots count from 1, so 5 is the last one, as in game_logs/log1.
+        engine.face_up.0[4] = Some(train(84));
+        engine.apply(0, Action::PickOpen { slot: 5 }).unwrap();
+        assert_eq!(engine.players[0].hand.last(), Some(&train(84)));
+        assert_eq!(engine.players[0].hand.len(), 6);
+        assert!(engine.state().action_required(1));
+
+        engine.apply(1, Action::Tickets).unwrap();
+        let drawn: Vec<_> = match engine.state() {
+            GameState::Turn {
+                state: TurnState::SelectingTickets(drawn),
+                ..
+            } => drawn.iter().map(|t| t.id).collect(),
+            state => panic!("{:?}", state),
+        };
+        assert_eq!(drawn.len(), 3);
+        assert_eq!(
+            engine.apply(
+                1,
+                Action::ReturnTickets {
+                    tickets: drawn.clone()
+                }
+            ),
+            Err(MoveError::TooFewTickets {
+                kept: 0,
+                required: 1
+            })
+        );
+        engine
+            .apply(
+                1,
+                Action::ReturnTickets {
+                    tickets: drawn[1..].to_vec(),
+                },
+            )
+            .unwrap();
+        assert_eq!(engine.players[1].tickets.len(), 3);
+        assert!(engine.state().action_required(0));
+    }
+
+    #[test]
+    fn test_last_round() {
+        let mut engine = start_game(27683789);
+        engine.players[0].trains = 3;
+        engine.players[0].hand = (60..72).map(train).collect();
+
+        // Amsterdam-Bruxelles, a black route of length 1, leaves player 0 with 2 trains.
+        let cards = engine.players[0].hand[..1].to_vec();
+        engine.apply(0, Action::Route { route: 0, cards }).unwrap();
+        assert_eq!(engine.last_player, Some(0));
+        for &player in &[1, 0] {
+            engine.apply(player, Action::PickDeck).unwrap();
+            engine.apply(player, Action::PickDeck).unwrap();
+        }
+        match engine.state() {
+            GameState::Finished(scores) => assert_eq!(scores, &engine.scores()),
+            state => panic!("{:?}", state),
+        }
+        assert_eq!(engine.apply(1, Action::PickDeck), Err(MoveError::GameOver));
+    }
+
+    #[test]
+    fn test_double_routes() {
+        let mut engine = start_game(27683789);
+        let routes = engine.map().routes();
+        engine.players[0].hand = (60..72).map(train).collect();
+        let cards = engine.players[0].hand[..3].to_vec();
+        let claim = Action::Route { route: 17, cards };
+
+        // Berlin-Frankfurt is a double route, made up of routes 16 and 17.
+        engine.players[0].routes.push(&routes[16]);
+        assert_eq!(
+            engine.apply(0, claim.clone()),
+            Err(MoveError::OwnSibling {
+                route: 17,
+                sibling: 16
+            })
+        );
+        engine.players[0].routes.clear();
+        engine.players[1].routes.push(&routes[16]);
+        assert_eq!(
+            engine.apply(0, claim),
+            Err(MoveError::SiblingClaimed {
+                route: 17,
+                sibling: 16
+            })
+        );
+    }
 }
@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{rand::Rand, City, Colour, LoadError, Route, Ticket, Train};
+
+use super::{Map, Points};
+
+/// A map's board and components, as described by its data file.
+#[derive(Debug)]
+pub struct MapData {
+    /// Id of the map in `Welcome.Options.map`.
+    pub id: u32,
+    pub name: &'static str,
+    pub cities: &'static [City],
+    pub routes: &'static [Route],
+    pub tickets: &'static [Ticket],
+    /// The cards in the train deck, by colour in card id order.
+    pub deck: Vec<(Colour, u32)>,
+    pub players: PlayerCount,
+    pub pieces: Pieces,
+    pub initial: Initial,
+    pub points: Points,
+}
+
+impl MapData {
+    /// Looks up a card in this map's train deck.
+    pub fn train(&self, id: u8) -> Option<Train> {
+        Train::from_id(&self.deck, id)
+    }
+}
+
+/// How many players can play on a map.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlayerCount {
+    pub min: u32,
+    pub max: u32,
+    /// The fewest players for both tracks of a double route to be claimed.
+    pub doubles: u32,
+}
+
+/// The pieces each player starts with.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pieces {
+    pub trains: u32,
+    pub stations: u32,
+    /// The final round starts once a player has this many trains or fewer.
+    pub last_round: u32,
+}
+
+/// The tickets dealt to each player at the start of the game.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Initial {
+    pub short: u32,
+    pub long: u32,
+    /// The fewest of the dealt tickets a player may keep.
+    pub keep: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct File {
+    id: u32,
+    name: String,
+    cities: Vec<String>,
+    routes: Vec<RouteDef>,
+    tickets: Vec<TicketDef>,
+    deck: Vec<DeckDef>,
+    players: PlayerCount,
+    pieces: Pieces,
+    initial: Initial,
+    points: Points,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RouteDef {
+    from: String,
+    to: String,
+    length: u32,
+    /// Missing for grey routes.
+    colour: Option<Colour>,
+    #[serde(default)]
+    tunnel: bool,
+    #[serde(default)]
+    locomotives: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TicketDef {
+    from: String,
+    to: String,
+    value: u32,
+    #[serde(default)]
+    long: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeckDef {
+    colour: Colour,
+    count: u32,
+}
+
+/// Parses and validates a map data file.
+///
+/// The engine hands out `'static` references into maps, so the result is leaked and each file
+/// should only be loaded once.
+pub fn load(src: &str) -> Result<&'static MapData, LoadError> {
+    let file: File = toml::from_str(src)?;
+
+    let mut ids = HashMap::new();
+    for (id, name) in file.cities.iter().enumerate() {
+        if ids.insert(name.as_str(), id).is_some() {
+            return Err(LoadError::DuplicateCity(name.clone()));
+        }
+    }
+    let find = |name: &String| {
+        ids.get(name.as_str())
+            .copied()
+            .ok_or_else(|| LoadError::UnknownCity(name.clone()))
+    };
+
+    let mut route_cities = Vec::new();
+    for (id, def) in file.routes.iter().enumerate() {
+        let invalid = |reason| LoadError::InvalidRoute {
+            route: id as u32,
+            reason,
+        };
+        let (city0, city1) = (find(&def.from)?, find(&def.to)?);
+        if city0 == city1 {
+            return Err(invalid("it starts and ends at the same city"));
+        }
+        if def.length == 0 || def.length as usize >= file.points.routes.len() {
+            return Err(invalid("no points are given for its length"));
+        }
+        if def.colour == Some(Colour::Rainbow) {
+            return Err(invalid("routes can't be rainbow"));
+        }
+        if def.locomotives > def.length {
+            return Err(invalid("it needs more locomotives than its length"));
+        }
+        if def.tunnel && def.locomotives > 0 {
+            return Err(invalid("it can't be both a tunnel and a ferry"));
+        }
+        route_cities.push((city0, city1));
+    }
+    let ends = |(c0, c1): (usize, usize)| (c0.min(c1), c0.max(c1));
+    let mut siblings = Vec::new();
+    for (id, &cities) in route_cities.iter().enumerate() {
+        let mut parallel = route_cities
+            .iter()
+            .enumerate()
+            .filter(|&(other, &c)| other != id && ends(c) == ends(cities))
+            .map(|(other, _)| other as u32);
+        siblings.push(parallel.next());
+        if parallel.next().is_some() {
+            return Err(LoadError::InvalidRoute {
+                route: id as u32,
+                reason: "only double routes are supported",
+            });
+        }
+    }
+
+    let mut ticket_cities = Vec::new();
+    for (id, def) in file.tickets.iter().enumerate() {
+        let (city0, city1) = (find(&def.from)?, find(&def.to)?);
+        if city0 == city1 {
+            return Err(LoadError::InvalidTicket {
+                ticket: id as u32,
+                reason: "it starts and ends at the same city",
+            });
+        }
+        ticket_cities.push((city0, city1));
+    }
+
+    let initial = &file.initial;
+    if initial.keep == 0 || initial.keep > initial.short + initial.long {
+        return Err(LoadError::InvalidDeal(
+            "players must keep between one and all of their tickets",
+        ));
+    }
+
+    if file.pieces.last_round >= file.pieces.trains {
+        return Err(LoadError::InvalidPieces(
+            "the game must not start in its final round",
+        ));
+    }
+    if file.players.min == 0 || file.players.min > file.players.max {
+        return Err(LoadError::InvalidPlayerCount);
+    }
+    let long = file.tickets.iter().filter(|t| t.long).count() as u32;
+    let short = file.tickets.len() as u32 - long;
+    if initial.short * file.players.max > short || initial.long * file.players.max > long {
+        return Err(LoadError::InvalidDeal(
+            "there aren't enough tickets to deal to the most players",
+        ));
+    }
+
+    let mut colours = Vec::new();
+    for def in &file.deck {
+        if colours.contains(&def.colour) {
+            return Err(LoadError::InvalidDeck("a colour is listed more than once"));
+        }
+        colours.push(def.colour);
+    }
+    let size: u32 = file.deck.iter().map(|d| d.count).sum();
+    if size == 0 || size > 256 {
+        return Err(LoadError::InvalidDeck(
+            "it must have between 1 and 256 cards",
+        ));
+    }
+
+    let cities: &'static [City] = leak(
+        file.cities
+            .into_iter()
+            .enumerate()
+            .map(|(id, name)| City {
+                id: id as u32,
+                name: Box::leak(name.into_boxed_str()),
+            })
+            .collect(),
+    );
+    let routes = leak(
+        file.routes
+            .into_iter()
+            .zip(route_cities)
+            .zip(siblings)
+            .enumerate()
+            .map(|(id, ((def, (city0, city1)), sibling))| Route {
+                id: id as u32,
+                city0: &cities[city0],
+                city1: &cities[city1],
+                length: def.length,
+                colour: def.colour,
+                tunnel: def.tunnel,
+                locomotives: def.locomotives,
+                sibling,
+            })
+            .collect(),
+    );
+    let tickets = leak(
+        file.tickets
+            .into_iter()
+            .zip(ticket_cities)
+            .enumerate()
+            .map(|(id, (def, (city0, city1)))| Ticket {
+                id: id as u32,
+                city0: &cities[city0],
+                city1: &cities[city1],
+                value: def.value,
+                long: def.long,
+            })
+            .collect(),
+    );
+
+    Ok(Box::leak(Box::new(MapData {
+        id: file.id,
+        name: Box::leak(file.name.into_boxed_str()),
+        cities,
+        routes,
+        tickets,
+        deck: file.deck.iter().map(|d| (d.colour, d.count)).collect(),
+        players: file.players,
+        pieces: file.pieces,
+        initial: file.initial,
+        points: file.points,
+    })))
+}
+
+fn leak<T>(vec: Vec<T>) -> &'static [T] {
+    Box::leak(vec.into_boxed_slice())
+}
+
+/// A game's copy of a map, holding its ticket decks.
+pub struct DataMap {
+    data: &'static MapData,
+    short: Vec<&'static Ticket>,
+    long: Vec<&'static Ticket>,
+}
+
+impl DataMap {
+    pub fn new(data: &'static MapData, rand: &mut Rand) -> Self {
+        let (long, short): (Vec<_>, Vec<_>) = data.tickets.iter().partition(|t| t.long);
+        let mut shuffle = |mut vec: Vec<_>| {
+            let mut shuffled = Vec::new();
+            for i in (2..=vec.len()).rev() {
+                let idx = rand.uniform(0, i as u32);
+                let val = vec.remove(idx as usize);
+                shuffled.push(val);
+            }
+            shuffled.append(&mut vec);
+            shuffled
+        };
+        let short = shuffle(short);
+        let long = shuffle(long);
+
+        DataMap { data, short, long }
+    }
+}
+
+impl Map for DataMap {
+    fn data(&self) -> &'static MapData {
+        self.data
+    }
+
+    fn initial_tickets(&mut self, players: u32) -> Vec<Vec<&'static Ticket>> {
+        let initial = &self.data.initial;
+        (0..players)
+            .map(|_| {
+                let short = self.short.drain(..initial.short as usize);
+                let long = self.long.drain(..initial.long as usize);
+                short.chain(long).collect()
+            })
+            .collect()
+    }
+
+    fn return_initial_tickets(&mut self, tickets: Vec<&'static Ticket>) {
+        // Long routes that weren't kept are out of the game, the rest go back in the deck.
+        self.short.extend(tickets.into_iter().filter(|t| !t.long));
+    }
+
+    fn draw_ticket(&mut self) -> Option<&'static Ticket> {
+        if self.short.is_empty() {
+            None
+        } else {
+            Some(self.short.remove(0))
+        }
+    }
+
+    fn return_tickets(&mut self, tickets: Vec<&'static Ticket>) {
+        self.short.extend(tickets);
+    }
+
+    fn tickets_left(&self) -> usize {
+        self.short.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TINY: &str = r#"
+        id = 99
+        name = "Tiny"
+        cities = ["A", "B", "C"]
+        routes = [
+            { from = "A", to = "B", length = 1, colour = "Red" },
+            { from = "A", to = "B", length = 1 },
+            { from = "B", to = "C", length = 2, tunnel = true },
+        ]
+        tickets = [{ from = "A", to = "C", value = 3 }, { from = "A", to = "B", value = 1 }]
+        deck = [{ colour = "Red", count = 10 }, { colour = "Rainbow", count = 2 }]
+
+        [players]
+        min = 1
+        max = 2
+        doubles = 2
+
+        [pieces]
+        trains = 10
+        stations = 0
+        last_round = 2
+
+        [initial]
+        short = 1
+        long = 0
+        keep = 1
+
+        [points]
+        routes = [0, 1, 2]
+        station = 0
+        longest = 5
+    "#;
+
+    #[test]
+    fn test_load() {
+        let data = load(TINY).unwrap();
+        assert_eq!(data.routes[0].sibling, Some(1));
+        assert_eq!(data.routes[2].city1.name, "C");
+        assert_eq!(data.deck, vec![(Colour::Red, 10), (Colour::Rainbow, 2)]);
+
+        let bad = TINY.replace(r#"to = "C", length = 2"#, r#"to = "D", length = 2"#);
+        assert_eq!(
+            load(&bad).unwrap_err().to_string(),
+            "Route or ticket mentions unknown city D"
+        );
+        let bad = TINY.replace("length = 2", "length = 3");
+        assert!(matches!(
+            load(&bad),
+            Err(LoadError::InvalidRoute { route: 2, .. })
+        ));
+        let bad = TINY.replace("max = 2", "max = 3");
+        assert!(matches!(load(&bad), Err(LoadError::InvalidDeal(_))));
+    }
+}
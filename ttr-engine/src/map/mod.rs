@@ -1,17 +1,111 @@
-use crate::{rand::Rand, City, Ticket};
+use lazy_static::lazy_static;
+use serde::Deserialize;
 
+use crate::{rand::Rand, City, Route, SetupError, Ticket};
+
+/// Declares a map whose data file is built into the engine, loading it on first use.
+macro_rules! builtin {
+    ($name:ident, $id:expr, $src:expr) => {
+        pub struct $name;
+
+        impl $name {
+            pub const ID: u32 = $id;
+        }
+
+        impl crate::map::Builtin for $name {
+            fn data() -> &'static crate::map::MapData {
+                lazy_static::lazy_static! {
+                    static ref DATA: &'static crate::map::MapData = crate::map::load($src).unwrap();
+                }
+                *DATA
+            }
+        }
+    };
+}
+
+mod data;
 mod europe;
+mod usa;
 
+pub use data::{load, DataMap, Initial, MapData, Pieces, PlayerCount};
 pub use europe::Europe;
+pub use usa::Usa;
+
+/// How many points each part of the game is worth on a map.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Points {
+    /// Points for a claimed route, indexed by its length.
+    pub routes: Vec<u32>,
+    /// Points for each station left unbuilt.
+    pub station: u32,
+    /// Bonus for the longest continuous path.
+    pub longest: u32,
+}
 
 pub trait Map {
-    fn new(rand: &mut Rand) -> Self
-    where
-        Self: Sized;
+    /// The board and components this map was set up from.
+    fn data(&self) -> &'static MapData;
 
     fn initial_tickets(&mut self, players: u32) -> Vec<Vec<&'static Ticket>>;
 
-    fn draw_ticket(&mut self) -> &'static Ticket;
+    /// The fewest of the initial tickets a player may keep.
+    fn initial_keep(&self) -> u32 {
+        self.data().initial.keep
+    }
+
+    /// Disposes of the initial tickets a player didn't keep.
+    fn return_initial_tickets(&mut self, tickets: Vec<&'static Ticket>);
+
+    /// Takes the top ticket from the deck, if there are any left.
+    fn draw_ticket(&mut self) -> Option<&'static Ticket>;
+
+    /// Puts tickets on the bottom of the deck, in the order given.
+    fn return_tickets(&mut self, tickets: Vec<&'static Ticket>);
+
+    /// Number of tickets left in the deck.
+    fn tickets_left(&self) -> usize;
+
+    fn cities(&self) -> &'static [City] {
+        self.data().cities
+    }
+
+    fn routes(&self) -> &'static [Route] {
+        self.data().routes
+    }
+
+    fn tickets(&self) -> &'static [Ticket] {
+        self.data().tickets
+    }
+
+    fn points(&self) -> &'static Points {
+        &self.data().points
+    }
+}
+
+/// A map whose data file is built into the engine.
+pub trait Builtin {
+    fn data() -> &'static MapData;
+
+    /// Sets up a copy of the map for a game, shuffling its tickets.
+    fn map(rand: &mut Rand) -> DataMap {
+        DataMap::new(Self::data(), rand)
+    }
+}
+
+lazy_static! {
+    static ref MAPS: Vec<&'static MapData> = vec![Usa::data(), Europe::data()];
+}
+
+/// Every map built into the engine.
+pub fn maps() -> &'static [&'static MapData] {
+    MAPS.as_slice()
+}
 
-    fn cities(&self) -> &'static [City];
+/// Finds a built in map by its id in `Welcome.Options.map`.
+pub fn lookup(id: u32) -> Result<&'static MapData, SetupError> {
+    MAPS.iter()
+        .copied()
+        .find(|m| m.id == id)
+        .ok_or(SetupError::UnknownMap(id))
 }
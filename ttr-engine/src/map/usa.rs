@@ -0,0 +1,30 @@
+// The protocol map id is assumed, not yet confirmed from a `Welcome` capture (see ids.md), so
+// don't rely on it to pick the board when replaying or intercepting real games.
+builtin!(Usa, 0, include_str!("../../resources/usa/map.toml"));
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        map::{Builtin, Map},
+        rand::Rand,
+    };
+
+    #[test]
+    fn test_usa() {
+        let data = Usa::data();
+        assert_eq!(data.id, Usa::ID);
+        assert_eq!(data.cities.len(), 36);
+        assert_eq!(data.routes.len(), 100);
+        assert_eq!(data.routes.iter().map(|r| r.length).sum::<u32>(), 309);
+        assert!(data.routes.iter().all(|r| !r.tunnel && r.locomotives == 0));
+
+        let mut usa = Usa::map(&mut Rand::new(1));
+        let dealt = usa.initial_tickets(3);
+        assert!(dealt.iter().all(|tickets| tickets.len() == 3));
+        assert_eq!(usa.initial_keep(), 2);
+        usa.return_initial_tickets(dealt[0][2..].to_vec());
+        let rest: Vec<_> = std::iter::from_fn(|| usa.draw_ticket()).collect();
+        assert_eq!(rest.len(), 30 - 9 + 1);
+    }
+}
@@ -0,0 +1,150 @@
+use super::{payment, Action, Colour, Cost, Engine, GameState, Ticket, TurnState};
+
+impl Engine {
+    /// Every action `player` could legally take now. Payments are listed once per distinct
+    /// colour and number of locomotives, rather than once per combination of card ids.
+    pub fn legal_moves(&self, player: u32) -> Vec<Action> {
+        if player as usize >= self.players.len() || !self.state.action_required(player) {
+            return Vec::new();
+        }
+        let state = match &self.state {
+            GameState::InitialTickets(states) => {
+                let options = &states[player as usize].options;
+                return subsets(options, self.map.initial_keep())
+                    .map(|kept| Action::ChooseFirstTickets {
+                        tickets: ids(options, |t| kept.contains(t)),
+                    })
+                    .collect();
+            }
+            GameState::Turn { state, .. } => state,
+            GameState::Finished(_) => return Vec::new(),
+        };
+
+        let p = &self.players[player as usize];
+        let mut moves = Vec::new();
+        match state {
+            TurnState::Start => {
+                for route in self.map.routes() {
+                    if self.check_route(player, route).is_ok() {
+                        moves.extend(Cost::route(route).payments(&p.hand).into_iter().map(
+                            |payment| Action::Route {
+                                route: route.id,
+                                cards: payment.cards,
+                            },
+                        ));
+                    }
+                }
+                let cost = Cost::station(p.stations.len() as u32);
+                for city in self.map.cities() {
+                    if self.check_station(player, city).is_ok() {
+                        moves.extend(cost.payments(&p.hand).into_iter().map(|payment| {
+                            Action::Station {
+                                city: city.id,
+                                cards: payment.cards,
+                            }
+                        }));
+                    }
+                }
+                if self.map.tickets_left() > 0 {
+                    moves.push(Action::Tickets);
+                }
+                self.train_picks(true, &mut moves);
+            }
+            TurnState::PickAnotherTrain { .. } => self.train_picks(false, &mut moves),
+            TurnState::SelectingTickets(drawn) => {
+                moves.extend(subsets(drawn, 1).map(|kept| Action::ReturnTickets {
+                    tickets: ids(drawn, |t| !kept.contains(t)),
+                }));
+            }
+            TurnState::Tunnel {
+                route,
+                cards,
+                extra,
+                ..
+            } => {
+                // The cards already played were checked when the claim started.
+                let colour = Cost::route(route).check(&p.hand, cards).unwrap();
+                let hand = payment::remaining(&p.hand, cards).unwrap();
+                moves.extend(
+                    Cost::tunnel(*extra, colour)
+                        .payments(&hand)
+                        .into_iter()
+                        .map(|payment| Action::PayTunnel {
+                            cards: payment.cards,
+                        }),
+                );
+                moves.push(Action::AbandonTunnel);
+            }
+        }
+        moves
+    }
+
+    fn train_picks(&self, first: bool, moves: &mut Vec<Action>) {
+        for (slot, card) in self.face_up.0.iter().enumerate() {
+            match card {
+                Some(card) if first || card.colour() != Colour::Rainbow => {
+                    moves.push(Action::PickOpen {
+                        slot: slot as u32 + 1,
+                    })
+                }
+                _ => {}
+            }
+        }
+        if self.trains.cards().next().is_some() {
+            moves.push(Action::PickDeck);
+        }
+    }
+}
+
+/// Every selection of at least `min` of the tickets.
+fn subsets<'a>(
+    tickets: &'a [&'static Ticket],
+    min: u32,
+) -> impl Iterator<Item = Vec<&'static Ticket>> + 'a {
+    (0..1u32 << tickets.len())
+        .filter(move |mask| mask.count_ones() >= min)
+        .map(move |mask| {
+            (0..tickets.len())
+                .filter(|i| mask & (1 << i) != 0)
+                .map(|i| tickets[i])
+                .collect()
+        })
+}
+
+fn ids(tickets: &[&'static Ticket], pred: impl Fn(&&'static Ticket) -> bool) -> Vec<u32> {
+    tickets.iter().filter(|t| pred(t)).map(|t| t.id).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::map;
+
+    #[test]
+    fn test_legal_moves() {
+        // Engines can't be cloned, but replaying the same actions gives the same game.
+        let replay = |history: &[(u32, Action)]| {
+            let mut engine = Engine::new::<map::Europe>(27683789, 2, 0).unwrap();
+            for (player, action) in history {
+                engine.apply(*player, action.clone()).unwrap();
+            }
+            engine
+        };
+
+        let mut history = Vec::new();
+        // Four tickets are dealt and at least two must be kept.
+        assert_eq!(replay(&history).legal_moves(0).len(), 6 + 4 + 1);
+        for _ in 0..12 {
+            let engine = replay(&history);
+            let player = (0..2).find(|&p| engine.state().action_required(p)).unwrap();
+            let moves = engine.legal_moves(player);
+            assert!(!moves.is_empty());
+            for action in &moves {
+                let mut engine = replay(&history);
+                assert_eq!(engine.apply(player, action.clone()), Ok(()), "{:?}", action);
+            }
+            history.push((player, moves[moves.len() / 2].clone()));
+        }
+        assert!(replay(&history).legal_moves(2).is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use super::{City, Route};
+
+/// A continuous trail through a set of routes, using each route at most once.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Path {
+    pub length: u32,
+    /// The cities visited, in order. Empty if there are no routes.
+    pub cities: Vec<&'static City>,
+    /// The routes travelled, in order, so that `routes[i]` joins `cities[i]` and `cities[i + 1]`.
+    pub routes: Vec<&'static Route>,
+}
+
+struct Search<'a> {
+    routes: &'a [&'static Route],
+    adjacent: HashMap<u32, Vec<(usize, &'static City)>>,
+    used: Vec<bool>,
+    current: Path,
+    best: Path,
+}
+
+/// Finds the longest trail through `routes`.
+///
+/// A maximal trail either ends at cities with an odd number of routes, or uses every route of an
+/// Eulerian component, so the search only needs to start from odd-degree cities and one city of
+/// each component where every degree is even.
+pub fn longest_path(routes: &[&'static Route]) -> Path {
+    let mut adjacent: HashMap<u32, Vec<(usize, &'static City)>> = HashMap::new();
+    for (i, r) in routes.iter().enumerate() {
+        adjacent.entry(r.city0.id).or_default().push((i, r.city1));
+        adjacent.entry(r.city1.id).or_default().push((i, r.city0));
+    }
+
+    let mut search = Search {
+        routes,
+        adjacent,
+        used: vec![false; routes.len()],
+        current: Path::default(),
+        best: Path::default(),
+    };
+    for city in search.starts() {
+        search.current.cities.push(city);
+        search.visit(city);
+        search.current.cities.pop();
+    }
+    search.best
+}
+
+impl<'a> Search<'a> {
+    fn starts(&self) -> Vec<&'static City> {
+        let mut component: HashMap<u32, usize> = HashMap::new();
+        let mut starts = Vec::new();
+        for r in self.routes {
+            if component.contains_key(&r.city0.id) {
+                continue;
+            }
+            let idx = starts.len();
+            let mut odd = Vec::new();
+            let mut stack = vec![r.city0];
+            while let Some(city) = stack.pop() {
+                if component.insert(city.id, idx).is_some() {
+                    continue;
+                }
+                let adjacent = &self.adjacent[&city.id];
+                if adjacent.len() % 2 == 1 {
+                    odd.push(city);
+                }
+                stack.extend(adjacent.iter().map(|&(_, c)| c));
+            }
+            if odd.is_empty() {
+                starts.push(vec![r.city0]);
+            } else {
+                starts.push(odd);
+            }
+        }
+        starts.into_iter().flatten().collect()
+    }
+
+    fn visit(&mut self, city: &'static City) {
+        if self.current.length > self.best.length {
+            self.best = self.current.clone();
+        }
+        for i in 0..self.adjacent[&city.id].len() {
+            let (route, next) = self.adjacent[&city.id][i];
+            if self.used[route] {
+                continue;
+            }
+            self.used[route] = true;
+            self.current.length += self.routes[route].length;
+            self.current.routes.push(self.routes[route]);
+            self.current.cities.push(next);
+
+            self.visit(next);
+
+            self.current.cities.pop();
+            self.current.routes.pop();
+            self.current.length -= self.routes[route].length;
+            self.used[route] = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        map::{Builtin, Europe, Map},
+        rand::Rand,
+    };
+
+    #[test]
+    fn test_longest_path() {
+        let europe = Europe::map(&mut Rand::new(1));
+        let routes = europe.routes();
+        // A triangle (Berlin, Essen, Frankfurt) with tails to Amsterdam and Munchen.
+        // Only one tail can be used along with the whole triangle.
+        let claimed: Vec<_> = [15, 16, 55, 1, 32].iter().map(|&i| &routes[i]).collect();
+        assert_eq!(longest_path(&claimed[..3]).length, 7);
+
+        let path = longest_path(&claimed);
+        assert_eq!(path.length, 10);
+        assert_eq!(path.routes.len(), 4);
+        for (i, r) in path.routes.iter().enumerate() {
+            let (a, b) = (path.cities[i], path.cities[i + 1]);
+            assert!((r.city0 == a && r.city1 == b) || (r.city0 == b && r.city1 == a));
+        }
+
+        assert_eq!(longest_path(&[]), Path::default());
+    }
+}
@@ -0,0 +1,196 @@
+use super::{Colour, MoveError, Route, Train};
+
+/// The cards needed to claim a route, build a station or finish a tunnel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cost {
+    pub count: u32,
+    /// `None` if any single colour will do.
+    pub colour: Option<Colour>,
+    /// Locomotives that must be among the cards. Nothing else can stand in for them.
+    pub locomotives: u32,
+}
+
+/// One way of paying a cost from a hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Payment {
+    /// The colour the cards count as, `Rainbow` if they are all locomotives.
+    pub colour: Colour,
+    pub cards: Vec<Train>,
+}
+
+impl Cost {
+    pub fn route(route: &Route) -> Self {
+        Cost {
+            count: route.length,
+            colour: route.colour,
+            locomotives: route.locomotives,
+        }
+    }
+
+    /// Each station costs one more card than the last, all of a single colour.
+    pub fn station(built: u32) -> Self {
+        Cost {
+            count: built + 1,
+            colour: None,
+            locomotives: 0,
+        }
+    }
+
+    /// The extra cards for a tunnel. They must match the colour already played, so an all
+    /// locomotive payment can only be topped up with more locomotives.
+    pub fn tunnel(extra: u32, colour: Colour) -> Self {
+        Cost {
+            count: extra,
+            colour: Some(colour),
+            locomotives: 0,
+        }
+    }
+
+    /// Checks that `cards` can be taken from `hand` and pay this cost, returning their colour.
+    pub fn check(&self, hand: &[Train], cards: &[Train]) -> Result<Colour, MoveError> {
+        remaining(hand, cards)?;
+        if cards.len() != self.count as usize {
+            return Err(MoveError::WrongCardCount {
+                expected: self.count,
+                actual: cards.len() as u32,
+            });
+        }
+        let colour = single_colour(cards).ok_or(MoveError::WrongColour)?;
+        if let Some(required) = self.colour {
+            if colour != required && colour != Colour::Rainbow {
+                return Err(MoveError::WrongColour);
+            }
+        }
+        let locomotives = count(cards, Colour::Rainbow);
+        if locomotives < self.locomotives {
+            return Err(MoveError::NotEnoughLocomotives {
+                expected: self.locomotives,
+                actual: locomotives,
+            });
+        }
+        Ok(colour)
+    }
+
+    /// Every distinct way of paying this cost from `hand`, by colour and number of locomotives.
+    pub fn payments(&self, hand: &[Train]) -> Vec<Payment> {
+        let mut colours: Vec<_> = match self.colour {
+            Some(colour) => vec![colour],
+            None => hand.iter().map(|c| c.colour()).collect(),
+        };
+        colours.sort();
+        colours.dedup();
+
+        let available = count(hand, Colour::Rainbow);
+        let mut payments = Vec::new();
+        for colour in colours.into_iter().filter(|&c| c != Colour::Rainbow) {
+            let have = count(hand, colour);
+            // Payments of only locomotives are listed once, as `Rainbow`, below.
+            for locomotives in self.locomotives..self.count.min(available + 1) {
+                let coloured = self.count - locomotives;
+                if coloured <= have {
+                    payments.push(Payment {
+                        colour,
+                        cards: take(hand, colour, coloured)
+                            .chain(take(hand, Colour::Rainbow, locomotives))
+                            .collect(),
+                    });
+                }
+            }
+        }
+        if available >= self.count {
+            payments.push(Payment {
+                colour: Colour::Rainbow,
+                cards: take(hand, Colour::Rainbow, self.count).collect(),
+            });
+        }
+        payments
+    }
+}
+
+/// Returns what is left of `hand` after playing `cards`, or the first card that isn't in it.
+pub(crate) fn remaining(hand: &[Train], cards: &[Train]) -> Result<Vec<Train>, MoveError> {
+    let mut hand = hand.to_vec();
+    for card in cards {
+        match hand.iter().position(|c| c == card) {
+            Some(idx) => hand.swap_remove(idx),
+            None => return Err(MoveError::MissingCard(card.id())),
+        };
+    }
+    Ok(hand)
+}
+
+/// Returns the colour of a payment, or `None` if it mixes colours.
+/// A payment of only locomotives has colour `Rainbow`.
+fn single_colour(cards: &[Train]) -> Option<Colour> {
+    let colour = cards
+        .iter()
+        .map(|c| c.colour())
+        .find(|&c| c != Colour::Rainbow)
+        .unwrap_or(Colour::Rainbow);
+    if cards
+        .iter()
+        .all(|c| c.colour() == colour || c.colour() == Colour::Rainbow)
+    {
+        Some(colour)
+    } else {
+        None
+    }
+}
+
+fn count(cards: &[Train], colour: Colour) -> u32 {
+    cards.iter().filter(|c| c.colour() == colour).count() as u32
+}
+
+fn take(hand: &[Train], colour: Colour, n: u32) -> impl Iterator<Item = Train> + '_ {
+    hand.iter()
+        .copied()
+        .filter(move |c| c.colour() == colour)
+        .take(n as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::map::{Builtin, Europe};
+
+    #[test]
+    fn test_payments() {
+        let data = Europe::data();
+        let train = |id| data.train(id).unwrap();
+        // Two pink, one white and two locomotives.
+        let hand: Vec<_> = [0, 1, 12, 96, 97].iter().map(|&id| train(id)).collect();
+
+        // Amsterdam-London, a grey ferry of length 2 needing two locomotives.
+        let ferry = Cost::route(&data.routes[4]);
+        let payments = ferry.payments(&hand);
+        assert_eq!(payments.len(), 1);
+        assert_eq!(payments[0].colour, Colour::Rainbow);
+        assert_eq!(
+            ferry.check(&hand, &[train(0), train(96)]),
+            Err(MoveError::NotEnoughLocomotives {
+                expected: 2,
+                actual: 1
+            })
+        );
+
+        let colours: Vec<_> = Cost::station(2)
+            .payments(&hand)
+            .iter()
+            .map(|p| (p.colour, count(&p.cards, Colour::Rainbow)))
+            .collect();
+        assert_eq!(
+            colours,
+            vec![(Colour::Pink, 1), (Colour::Pink, 2), (Colour::White, 2)]
+        );
+        for payment in Cost::station(1).payments(&hand) {
+            assert_eq!(
+                Cost::station(1).check(&hand, &payment.cards),
+                Ok(payment.colour)
+            );
+        }
+        assert_eq!(
+            Cost::station(1).check(&hand, &[train(0), train(2)]),
+            Err(MoveError::MissingCard(2))
+        );
+    }
+}
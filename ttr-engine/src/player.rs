@@ -1,4 +1,4 @@
-use super::{Ticket, Train};
+use super::{map::Pieces, City, Route, Ticket, Train};
 
 #[derive(Debug)]
 pub struct Player {
@@ -6,15 +6,33 @@ pub struct Player {
     pub hand: Vec<Train>,
     pub tickets: Vec<&'static Ticket>,
     pub trains: u32,
+    pub routes: Vec<&'static Route>,
+    pub stations: Vec<&'static City>,
+    /// Number of stations the player started with.
+    pub max_stations: u32,
 }
 
 impl Player {
-    pub fn new(id: u32) -> Self {
+    pub fn new(id: u32, pieces: &Pieces) -> Self {
         Player {
             id,
             hand: Vec::new(),
             tickets: Vec::new(),
-            trains: 45, // TODO: This may be map dependent
+            trains: pieces.trains,
+            routes: Vec::new(),
+            stations: Vec::new(),
+            max_stations: pieces.stations,
+        }
+    }
+
+    pub fn stations_left(&self) -> u32 {
+        self.max_stations - self.stations.len() as u32
+    }
+
+    pub fn remove_cards(&mut self, cards: &[Train]) {
+        for card in cards {
+            let idx = self.hand.iter().position(|c| c == card).unwrap();
+            self.hand.swap_remove(idx);
         }
     }
 }
@@ -5,7 +5,11 @@ pub struct Rand(u32);
 
 impl Rand {
     pub fn new(seed: u32) -> Rand {
-        Rand(((seed as u64) % MOD) as _)
+        // Like std::minstd_rand, a seed that reduces to 0 is replaced with 1.
+        match (seed as u64) % MOD {
+            0 => Rand(1),
+            s => Rand(s as _),
+        }
     }
 
     pub fn gen(&mut self) -> u32 {
@@ -0,0 +1,89 @@
+use super::{map::Map, path::longest_path, player::Player, tickets::ticket_status};
+
+/// Points a player earned at the end of the game, split by source.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ScoreBreakdown {
+    pub routes: u32,
+    pub tickets_completed: u32,
+    pub tickets_failed: u32,
+    pub stations: u32,
+    pub longest: u32,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> i32 {
+        (self.routes + self.tickets_completed + self.stations + self.longest) as i32
+            - self.tickets_failed as i32
+    }
+}
+
+pub fn score(map: &dyn Map, players: &[Player]) -> Vec<ScoreBreakdown> {
+    let points = map.points();
+    let lengths: Vec<_> = players
+        .iter()
+        .map(|p| longest_path(&p.routes).length)
+        .collect();
+    let longest = lengths.iter().copied().max().unwrap_or(0);
+
+    players
+        .iter()
+        .zip(lengths)
+        .map(|(p, length)| {
+            let mut score = ScoreBreakdown {
+                routes: p
+                    .routes
+                    .iter()
+                    .map(|r| points.routes[r.length as usize])
+                    .sum(),
+                stations: p.stations_left() * points.station,
+                longest: if length == longest && longest > 0 {
+                    points.longest
+                } else {
+                    0
+                },
+                ..Default::default()
+            };
+            let status = ticket_status(players, p.id);
+            score.tickets_completed = status.completed.iter().map(|t| t.value).sum();
+            score.tickets_failed = status.failed.iter().map(|t| t.value).sum();
+            score
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        map::{Builtin, Europe},
+        rand::Rand,
+    };
+
+    #[test]
+    fn test_score() {
+        let europe = Europe::map(&mut Rand::new(1));
+        let routes = europe.routes();
+        let ticket = |id| europe.tickets().iter().find(|t| t.id == id).unwrap();
+
+        let mut p0 = Player::new(0, &europe.data().pieces);
+        p0.routes = vec![&routes[15], &routes[1], &routes[4]];
+        p0.tickets = vec![ticket(25)];
+        let mut p1 = Player::new(1, &europe.data().pieces);
+        p1.routes = vec![&routes[32]];
+        p1.tickets = vec![ticket(19)];
+
+        let scores = score(&europe, &[p0, p1]);
+        assert_eq!(
+            scores[0],
+            ScoreBreakdown {
+                routes: 8,
+                tickets_completed: 7,
+                tickets_failed: 0,
+                stations: 12,
+                longest: 10,
+            }
+        );
+        assert_eq!(scores[0].total(), 37);
+        assert_eq!(scores[1].total(), 9);
+    }
+}
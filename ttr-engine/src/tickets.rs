@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use super::{player::Player, Route, Ticket};
+
+/// Which of a player's tickets are currently connected.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TicketStatus {
+    pub completed: Vec<&'static Ticket>,
+    pub failed: Vec<&'static Ticket>,
+    /// The opponent route each station borrows, chosen to maximise the ticket score.
+    pub borrowed: Vec<&'static Route>,
+}
+
+impl TicketStatus {
+    pub fn score(&self) -> i32 {
+        self.completed.iter().map(|t| t.value as i32).sum::<i32>()
+            - self.failed.iter().map(|t| t.value as i32).sum::<i32>()
+    }
+}
+
+/// Works out which of `players[player]`'s tickets are complete.
+///
+/// Each station may use one route owned by an opponent that touches its city. Every combination
+/// is tried and the one completing the most valuable set of tickets is kept.
+pub fn ticket_status(players: &[Player], player: u32) -> TicketStatus {
+    let p = &players[player as usize];
+    let options: Vec<Vec<Option<&'static Route>>> = p
+        .stations
+        .iter()
+        .map(|city| {
+            let adjacent = players
+                .iter()
+                .filter(|o| o.id != p.id)
+                .flat_map(|o| o.routes.iter().copied())
+                .filter(|r| r.city0 == *city || r.city1 == *city);
+            // Not borrowing comes first so ties don't report useless borrows.
+            std::iter::once(None).chain(adjacent.map(Some)).collect()
+        })
+        .collect();
+
+    let mut best: Option<TicketStatus> = None;
+    let mut choice = vec![0; options.len()];
+    loop {
+        let borrowed: Vec<_> = choice
+            .iter()
+            .zip(&options)
+            .filter_map(|(&i, o)| o[i])
+            .collect();
+        let status = evaluate(p, borrowed);
+        let better = match &best {
+            Some(b) => status.score() > b.score(),
+            None => true,
+        };
+        if better {
+            best = Some(status);
+        }
+
+        // Advance to the next combination, stopping once every one has been tried.
+        match (0..choice.len()).find(|&i| choice[i] + 1 < options[i].len()) {
+            Some(i) => {
+                choice[i] += 1;
+                choice[..i].iter_mut().for_each(|c| *c = 0);
+            }
+            None => break,
+        }
+    }
+    best.unwrap()
+}
+
+fn evaluate(player: &Player, borrowed: Vec<&'static Route>) -> TicketStatus {
+    let mut components = Components::default();
+    for r in player.routes.iter().chain(&borrowed) {
+        components.join(r.city0.id, r.city1.id);
+    }
+
+    let (completed, failed) = player
+        .tickets
+        .iter()
+        .partition(|t| components.find(t.city0.id) == components.find(t.city1.id));
+    TicketStatus {
+        completed,
+        failed,
+        borrowed,
+    }
+}
+
+/// Union-find over city ids.
+#[derive(Default)]
+struct Components(HashMap<u32, u32>);
+
+impl Components {
+    fn find(&mut self, city: u32) -> u32 {
+        let parent = *self.0.entry(city).or_insert(city);
+        if parent == city {
+            return city;
+        }
+        let root = self.find(parent);
+        self.0.insert(city, root);
+        root
+    }
+
+    fn join(&mut self, a: u32, b: u32) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.0.insert(a, b);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        map::{Builtin, Europe, Map},
+        rand::Rand,
+    };
+
+    #[test]
+    fn test_station_borrowing() {
+        let europe = Europe::map(&mut Rand::new(1));
+        let routes = europe.routes();
+        let city = |name| europe.cities().iter().find(|c| c.name == name).unwrap();
+        let ticket = |id| europe.tickets().iter().find(|t| t.id == id).unwrap();
+
+        // Player 0 wants London-Berlin and owns London-Amsterdam and Essen-Berlin, but player 1
+        // holds both routes that would join them at Amsterdam or Essen.
+        let mut p0 = Player::new(0, &europe.data().pieces);
+        p0.routes = vec![&routes[4], &routes[15]];
+        p0.tickets = vec![ticket(25)];
+        let mut p1 = Player::new(1, &europe.data().pieces);
+        p1.routes = vec![&routes[1], &routes[32]];
+
+        let status = ticket_status(&[p0, p1], 0);
+        assert!(status.completed.is_empty());
+        assert!(status.borrowed.is_empty());
+
+        let mut p0 = Player::new(0, &europe.data().pieces);
+        p0.routes = vec![&routes[4], &routes[15]];
+        p0.tickets = vec![ticket(25)];
+        p0.stations = vec![city("Frankfurt"), city("Essen")];
+        let mut p1 = Player::new(1, &europe.data().pieces);
+        p1.routes = vec![&routes[1], &routes[32]];
+
+        let status = ticket_status(&[p0, p1], 0);
+        assert_eq!(status.completed, vec![ticket(25)]);
+        assert_eq!(status.borrowed, vec![&routes[1]]);
+        assert_eq!(status.score(), 7);
+    }
+}
@@ -1,6 +1,8 @@
+use serde::Deserialize;
+
 use super::rand::Rand;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
 pub enum Colour {
     Pink,
     White,
@@ -13,59 +15,90 @@ pub enum Colour {
     Rainbow,
 }
 
+/// A train card. Its id and colour are fixed by the map's deck.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Train(u8);
+pub struct Train {
+    id: u8,
+    colour: Colour,
+}
 
 pub struct TrainDeck {
     deck: Vec<Train>,
     discard: Vec<Train>,
 }
 
-impl From<u8> for Train {
-    fn from(v: u8) -> Train {
-        assert!(v < 110);
-        Train(v)
+impl Train {
+    /// Looks up a card in a deck made up of the given colours, numbered in order.
+    pub fn from_id(deck: &[(Colour, u32)], id: u8) -> Option<Train> {
+        let mut first = 0;
+        for &(colour, count) in deck {
+            if u32::from(id) < first + count {
+                return Some(Train { id, colour });
+            }
+            first += count;
+        }
+        None
     }
-}
 
-impl Train {
     pub fn id(self) -> u8 {
-        self.0
+        self.id
     }
 
     pub fn colour(self) -> Colour {
-        use Colour::*;
-
-        match self.0 {
-            0..=11 => Pink,
-            12..=23 => White,
-            24..=35 => Blue,
-            36..=47 => Yellow,
-            48..=59 => Orange,
-            60..=71 => Black,
-            72..=83 => Red,
-            84..=95 => Green,
-            96..=109 => Rainbow,
-            _ => unreachable!(),
-        }
+        self.colour
     }
 }
 
 impl TrainDeck {
-    pub fn new() -> Self {
-        let deck = (0..110).map(Train).collect();
+    pub fn new(deck: &[(Colour, u32)]) -> Self {
+        let deck = deck
+            .iter()
+            .flat_map(|&(colour, count)| (0..count).map(move |_| colour))
+            .enumerate()
+            .map(|(id, colour)| Train {
+                id: id as u8,
+                colour,
+            })
+            .collect();
         let discard = Vec::new();
 
         TrainDeck { deck, discard }
     }
 
-    pub fn deal_one(&mut self, rand: &mut Rand) -> Train {
+    /// Deals a card, shuffling the discard pile back in if the deck has run out.
+    /// Returns `None` once both the deck and the discard pile are empty.
+    pub fn deal_one(&mut self, rand: &mut Rand) -> Option<Train> {
+        if self.deck.is_empty() {
+            // Assumed, not checked against the app: the discards go back in the order they were
+            // discarded. Cards are dealt by index, so a different order would deal different
+            // cards for the same random numbers.
+            self.deck.append(&mut self.discard);
+        }
+        if self.deck.is_empty() {
+            return None;
+        }
         let idx = rand.uniform(0, self.deck.len() as u32) as usize;
-        self.deck.remove(idx)
+        Some(self.deck.remove(idx))
     }
 
+    /// Deals up to `num` cards, fewer if the deck and discard pile run out.
     pub fn deal(&mut self, rand: &mut Rand, num: usize) -> Vec<Train> {
-        (0..num).map(|_| self.deal_one(rand)).collect()
+        (0..num).map_while(|_| self.deal_one(rand)).collect()
+    }
+
+    /// Number of cards left in the deck, not counting the discard pile.
+    pub fn deck_len(&self) -> usize {
+        self.deck.len()
+    }
+
+    /// Number of cards in the discard pile, waiting to be shuffled back in.
+    pub fn discard_len(&self) -> usize {
+        self.discard.len()
+    }
+
+    /// Every card still available to be dealt, from the deck or the discard pile.
+    pub fn cards(&self) -> impl Iterator<Item = Train> + '_ {
+        self.deck.iter().chain(&self.discard).copied()
     }
 
     pub fn discard<I>(&mut self, cards: I)
@@ -79,15 +112,48 @@ impl TrainDeck {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::map::{Builtin, Europe};
 
     #[test]
     fn test_deal() {
         let mut rand = Rand::new(18446744073515119986u64 as u32);
-        let mut deck = TrainDeck::new();
+        let mut deck = TrainDeck::new(&Europe::data().deck);
 
         rand.discard(44);
 
         let deal: Vec<_> = deck.deal(&mut rand, 13).iter().map(|t| t.id()).collect();
         assert_eq!(vec![88, 90, 107, 7, 19, 3, 8, 9, 39, 51, 41, 34, 40], deal);
     }
+
+    #[test]
+    fn test_reshuffle() {
+        let mut rand = Rand::new(1);
+        let mut deck = TrainDeck::new(&Europe::data().deck);
+
+        let all = deck.deal(&mut rand, 110);
+        assert_eq!(all.len(), 110);
+        assert_eq!(deck.deal_one(&mut rand), None);
+
+        deck.discard(all[..3].iter().copied());
+        let mut dealt = deck.deal(&mut rand, 5);
+        dealt.sort();
+        let mut expected = all[..3].to_vec();
+        expected.sort();
+        assert_eq!(dealt, expected);
+        assert!(deck.discard.is_empty());
+    }
+
+    #[test]
+    fn test_from_id() {
+        let deck = [(Colour::Red, 3), (Colour::Rainbow, 2)];
+        assert_eq!(
+            Train::from_id(&deck, 2).map(Train::colour),
+            Some(Colour::Red)
+        );
+        assert_eq!(
+            Train::from_id(&deck, 4).map(Train::colour),
+            Some(Colour::Rainbow)
+        );
+        assert_eq!(Train::from_id(&deck, 5), None);
+    }
 }
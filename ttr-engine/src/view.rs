@@ -0,0 +1,190 @@
+use super::{
+    map::Map, tickets, Action, City, Engine, GameState, Route, ScoreBreakdown, Ticket,
+    TicketStatus, Train, TurnState,
+};
+
+/// The game as one seat sees it: its own hand and tickets, and only the public parts of everyone
+/// else's. Bots should be given a view rather than the `Engine`, so they can't cheat by accident.
+pub struct PlayerView<'a> {
+    engine: &'a Engine,
+    player: u32,
+}
+
+/// What a seat can see of another player.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opponent<'a> {
+    pub id: u32,
+    pub hand_size: usize,
+    pub tickets: usize,
+    pub trains: u32,
+    pub routes: &'a [&'static Route],
+    pub stations: &'a [&'static City],
+}
+
+#[derive(Debug)]
+pub enum ViewState<'a> {
+    /// Players are choosing their first tickets. `options` is this seat's deal, or `None` once it
+    /// has chosen.
+    InitialTickets {
+        options: Option<&'a [&'static Ticket]>,
+    },
+    /// It is `player`'s turn. `state` is `None` while an opponent looks at the tickets they drew.
+    Turn {
+        player: u32,
+        state: Option<&'a TurnState>,
+    },
+    Finished(&'a [ScoreBreakdown]),
+}
+
+impl Engine {
+    /// What `player` is allowed to know about the game, or `None` if there is no such player.
+    pub fn view(&self, player: u32) -> Option<PlayerView<'_>> {
+        if (player as usize) < self.players.len() {
+            Some(PlayerView {
+                engine: self,
+                player,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> PlayerView<'a> {
+    pub fn player(&self) -> u32 {
+        self.player
+    }
+
+    pub fn map(&self) -> &'a dyn Map {
+        self.engine.map.as_ref()
+    }
+
+    pub fn hand(&self) -> &'a [Train] {
+        &self.engine.players[self.player as usize].hand
+    }
+
+    pub fn tickets(&self) -> &'a [&'static Ticket] {
+        &self.engine.players[self.player as usize].tickets
+    }
+
+    pub fn trains(&self) -> u32 {
+        self.engine.players[self.player as usize].trains
+    }
+
+    pub fn routes(&self) -> &'a [&'static Route] {
+        &self.engine.players[self.player as usize].routes
+    }
+
+    pub fn stations(&self) -> &'a [&'static City] {
+        &self.engine.players[self.player as usize].stations
+    }
+
+    pub fn ticket_status(&self) -> TicketStatus {
+        tickets::ticket_status(&self.engine.players, self.player)
+    }
+
+    /// Every other player, in seat order.
+    pub fn opponents(&self) -> Vec<Opponent<'a>> {
+        self.engine
+            .players
+            .iter()
+            .filter(|p| p.id != self.player)
+            .map(|p| Opponent {
+                id: p.id,
+                hand_size: p.hand.len(),
+                tickets: p.tickets.len(),
+                trains: p.trains,
+                routes: &p.routes,
+                stations: &p.stations,
+            })
+            .collect()
+    }
+
+    /// The face up cards. `Action::PickOpen { slot }` takes the card at index `slot - 1`.
+    pub fn face_up(&self) -> [Option<Train>; 5] {
+        self.engine.face_up.0
+    }
+
+    pub fn deck_size(&self) -> usize {
+        self.engine.trains.deck_len()
+    }
+
+    pub fn discard_size(&self) -> usize {
+        self.engine.trains.discard_len()
+    }
+
+    pub fn tickets_left(&self) -> usize {
+        self.engine.map.tickets_left()
+    }
+
+    pub fn state(&self) -> ViewState<'a> {
+        match &self.engine.state {
+            GameState::InitialTickets(states) => {
+                let state = &states[self.player as usize];
+                ViewState::InitialTickets {
+                    options: match state.selected {
+                        None => Some(&state.options),
+                        Some(_) => None,
+                    },
+                }
+            }
+            GameState::Turn { player, state } => ViewState::Turn {
+                player: *player,
+                state: match state {
+                    TurnState::SelectingTickets(_) if *player != self.player => None,
+                    state => Some(state),
+                },
+            },
+            GameState::Finished(scores) => ViewState::Finished(scores),
+        }
+    }
+
+    pub fn legal_moves(&self) -> Vec<Action> {
+        self.engine.legal_moves(self.player)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::map;
+
+    #[test]
+    fn test_view() {
+        let mut engine = Engine::new::<map::Europe>(27683789, 2, 0).unwrap();
+        let dealt = match (engine.state(), engine.view(1).unwrap().state()) {
+            (GameState::InitialTickets(states), ViewState::InitialTickets { options }) => {
+                assert_eq!(options.unwrap(), &states[1].options[..]);
+                states[1].options.len()
+            }
+            state => panic!("{:?}", state),
+        };
+        for player in 0..2 {
+            let choice = engine.view(player).unwrap().legal_moves().pop().unwrap();
+            engine.apply(player, choice).unwrap();
+        }
+        engine.apply(0, Action::Tickets).unwrap();
+
+        let view = engine.view(1).unwrap();
+        assert!(engine.view(2).is_none());
+        assert!(matches!(
+            view.state(),
+            ViewState::Turn {
+                player: 0,
+                state: None
+            }
+        ));
+        let opponents = view.opponents();
+        assert_eq!(opponents.len(), 1);
+        assert_eq!(opponents[0].hand_size, 4);
+        assert_eq!(opponents[0].tickets, dealt);
+        assert_eq!(view.deck_size(), 110 - 8 - 5);
+        assert!(engine
+            .view(0)
+            .unwrap()
+            .legal_moves()
+            .contains(&Action::ReturnTickets {
+                tickets: Vec::new()
+            }));
+    }
+}